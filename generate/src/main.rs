@@ -152,7 +152,7 @@ fn create_presentation(
     serialization_format: SDJWTSerializationFormat,
     disclosed_claims: &serde_json::Map<String, serde_json::Value>
 ) -> Result<String> {
-    let mut holder = SDJWTHolder::new(sd_jwt.to_string(), serialization_format).unwrap();
+    let holder = SDJWTHolder::new(sd_jwt.to_string(), serialization_format).unwrap();
 
     let presentation = holder
         .create_presentation(
@@ -163,7 +163,7 @@ fn create_presentation(
             None
         ).unwrap();
 
-    Ok(presentation)
+    Ok(presentation.into())
 }
 
 fn verify_presentation(
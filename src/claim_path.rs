@@ -0,0 +1,219 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+
+const ROOT: char = '$';
+
+/// A segment of a [ClaimPath].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathSegment {
+    /// Name of an object property.
+    Key(String),
+    /// Index of an array element.
+    Index(usize),
+}
+
+/// ClaimPath identifies a claim within the claims of an SD-JWT using a simple JSONPath,
+/// e.g. `$.address.country`, `$.nationalities[1]` or `$['place of birth']`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClaimPath(Vec<PathSegment>);
+
+impl ClaimPath {
+    /// The path of the whole claims object, i.e. `$`.
+    pub fn root() -> Self {
+        ClaimPath(Vec::new())
+    }
+
+    pub fn from_segments(segments: Vec<PathSegment>) -> Self {
+        ClaimPath(segments)
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Path of the property `key` of the claim identified by this path.
+    pub fn key(&self, key: &str) -> Self {
+        let mut segments = self.0.clone();
+        segments.push(PathSegment::Key(key.to_owned()));
+        ClaimPath(segments)
+    }
+
+    /// Path of the element `idx` of the claim identified by this path.
+    pub fn index(&self, idx: usize) -> Self {
+        let mut segments = self.0.clone();
+        segments.push(PathSegment::Index(idx));
+        ClaimPath(segments)
+    }
+
+    /// Path of the parent claim, `None` for the root.
+    pub fn parent(&self) -> Option<Self> {
+        self.0
+            .split_last()
+            .map(|(_, parent)| ClaimPath(parent.to_vec()))
+    }
+
+    /// True if `self` is `other` or one of its descendants.
+    pub fn starts_with(&self, other: &ClaimPath) -> bool {
+        self.0.starts_with(&other.0)
+    }
+
+    /// Returns the claim identified by this path.
+    pub fn get<'a>(&self, claims: &'a Value) -> Option<&'a Value> {
+        self.0.iter().try_fold(claims, |value, segment| match segment {
+            PathSegment::Key(key) => value.get(key),
+            PathSegment::Index(idx) => value.get(idx),
+        })
+    }
+
+    /// Removes the claim identified by this path, returning it.
+    pub fn remove(&self, claims: &mut Value) -> Option<Value> {
+        let (last, parent) = self.0.split_last()?;
+        let parent = parent.iter().try_fold(claims, |value, segment| match segment {
+            PathSegment::Key(key) => value.get_mut(key),
+            PathSegment::Index(idx) => value.get_mut(idx),
+        })?;
+        match (last, parent) {
+            (PathSegment::Key(key), Value::Object(object)) => object.shift_remove(key),
+            (PathSegment::Index(idx), Value::Array(array)) if *idx < array.len() => {
+                Some(array.remove(*idx))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_quoted_key(chars: &mut std::iter::Peekable<std::str::Chars>, path: &str) -> Result<String> {
+        let quote = chars.next().ok_or(Self::invalid(path))?;
+        let mut key = String::new();
+        loop {
+            match chars.next() {
+                Some('\\') => key.push(chars.next().ok_or(Self::invalid(path))?),
+                Some(c) if c == quote => break,
+                Some(c) => key.push(c),
+                None => return Err(Self::invalid(path)),
+            }
+        }
+        match chars.next() {
+            Some(']') => Ok(key),
+            _ => Err(Self::invalid(path)),
+        }
+    }
+
+    fn invalid(path: &str) -> Error {
+        Error::InvalidPath(format!("Invalid JSONPath `{}`", path))
+    }
+}
+
+impl FromStr for ClaimPath {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        let mut chars = path.chars().peekable();
+        if chars.next() != Some(ROOT) {
+            return Err(Self::invalid(path));
+        }
+
+        let mut segments = Vec::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => {
+                    let mut key = String::new();
+                    while let Some(c) = chars.next_if(|c| *c != '.' && *c != '[') {
+                        key.push(c);
+                    }
+                    if key.is_empty() {
+                        return Err(Self::invalid(path));
+                    }
+                    segments.push(PathSegment::Key(key));
+                }
+                '[' => match chars.peek() {
+                    Some('\'') | Some('"') => {
+                        segments.push(PathSegment::Key(Self::parse_quoted_key(&mut chars, path)?));
+                    }
+                    _ => {
+                        let mut idx = String::new();
+                        while let Some(c) = chars.next_if(|c| *c != ']') {
+                            idx.push(c);
+                        }
+                        chars.next().ok_or(Self::invalid(path))?;
+                        let idx = idx.parse().map_err(|_| Self::invalid(path))?;
+                        segments.push(PathSegment::Index(idx));
+                    }
+                },
+                _ => return Err(Self::invalid(path)),
+            }
+        }
+
+        Ok(ClaimPath(segments))
+    }
+}
+
+impl fmt::Display for ClaimPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", ROOT)?;
+        for segment in &self.0 {
+            match segment {
+                PathSegment::Key(key) if !key.is_empty() && !key.contains(['.', '[', ']', '\'', '\\']) => {
+                    write!(f, ".{}", key)?
+                }
+                PathSegment::Key(key) => {
+                    write!(f, "['{}']", key.replace('\\', "\\\\").replace('\'', "\\'"))?
+                }
+                PathSegment::Index(idx) => write!(f, "[{}]", idx)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::claim_path::{ClaimPath, PathSegment};
+
+    #[test]
+    fn test_parse_and_display() {
+        for (path, segments) in [
+            ("$", vec![]),
+            ("$.address.country", vec![PathSegment::Key("address".into()), PathSegment::Key("country".into())]),
+            ("$.nationalities[1]", vec![PathSegment::Key("nationalities".into()), PathSegment::Index(1)]),
+            ("$.baz[0][1]", vec![PathSegment::Key("baz".into()), PathSegment::Index(0), PathSegment::Index(1)]),
+            ("$['place.of birth']", vec![PathSegment::Key("place.of birth".into())]),
+            ("$.vct#integrity", vec![PathSegment::Key("vct#integrity".into())]),
+        ] {
+            let parsed: ClaimPath = path.parse().unwrap();
+            assert_eq!(parsed.segments(), segments.as_slice());
+            assert_eq!(parsed.to_string(), path);
+        }
+        assert_eq!("$[\"address\"]['country']".parse::<ClaimPath>().unwrap().to_string(), "$.address.country");
+
+        for invalid in ["", "address", "$.", "$..a", "$[a]", "$['a'", "$[1", "$a"] {
+            assert!(invalid.parse::<ClaimPath>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_get_and_remove() {
+        let mut claims = json!({"address": {"country": "DE"}, "nationalities": ["US", "CA"]});
+        let country: ClaimPath = "$.address.country".parse().unwrap();
+        let nationality: ClaimPath = "$.nationalities[0]".parse().unwrap();
+
+        assert_eq!(country.get(&claims), Some(&json!("DE")));
+        assert_eq!(nationality.get(&claims), Some(&json!("US")));
+        assert_eq!(ClaimPath::root().key("missing").get(&claims), None);
+        assert!(country.starts_with(&country.parent().unwrap()));
+
+        assert_eq!(nationality.remove(&mut claims), Some(json!("US")));
+        assert_eq!(country.remove(&mut claims), Some(json!("DE")));
+        assert_eq!(country.remove(&mut claims), None);
+        assert_eq!(claims, json!({"address": {}, "nationalities": ["CA"]}));
+    }
+}
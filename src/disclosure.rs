@@ -1,101 +1,545 @@
-use crate::utils::{base64_hash, base64url_encode};
+use std::fmt;
+use std::fmt::Write as _;
+use std::io;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
 #[cfg(not(feature = "mock_salts"))]
 use crate::utils::generate_salt;
 #[cfg(feature = "mock_salts")]
 use crate::utils::generate_salt_mock;
+use crate::utils::{base64_hash, base64url_decode, base64url_encode};
+use crate::{SDJWTCommon, ARRAY_DISCLOSURE_LEN, DEFAULT_DIGEST_ALG, OBJECT_DISCLOSURE_LEN};
+use serde::Serialize;
+use serde_json::ser::{Formatter, Serializer};
 use serde_json::Value;
+use sha2::{Digest, Sha384, Sha512};
 
+/// Hash algorithm of the digests of disclosures, identified by its name in the IANA "Named Information Hash Algorithm" registry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+}
 
-#[derive(Debug)]
-pub(crate) struct SDJWTDisclosure {
-    pub raw_b64: String,
-    pub hash: String,
+impl DigestAlgorithm {
+    /// The name of the algorithm, as in the `_sd_alg` claim.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => DEFAULT_DIGEST_ALG,
+            DigestAlgorithm::Sha384 => "sha-384",
+            DigestAlgorithm::Sha512 => "sha-512",
+        }
+    }
+
+    /// Computes the base64url-encoded digest of `data`.
+    pub fn digest(&self, data: &[u8]) -> String {
+        match self {
+            DigestAlgorithm::Sha256 => base64_hash(data),
+            DigestAlgorithm::Sha384 => base64url_encode(&Sha384::digest(data)),
+            DigestAlgorithm::Sha512 => base64url_encode(&Sha512::digest(data)),
+        }
+    }
 }
 
-impl SDJWTDisclosure  {
-    pub(crate) fn new<V>(key: Option<String>, value: V) -> Self where V: ToString {
-        #[cfg(not(feature = "mock_salts"))]
-            let salt = generate_salt();
-        let mut value_str = value.to_string();
+impl FromStr for DigestAlgorithm {
+    type Err = Error;
 
-        #[cfg(feature = "mock_salts")]
-            let salt = {
-            value_str = value_str
-                .replace(":[", ": [")
-                .replace(',', ", ")
-                .replace("\":", "\": ")
-                .replace("\":  ", "\": ");
-            generate_salt_mock()
-        };
+    fn from_str(alg: &str) -> Result<Self> {
+        match alg {
+            DEFAULT_DIGEST_ALG => Ok(DigestAlgorithm::Sha256),
+            "sha-384" => Ok(DigestAlgorithm::Sha384),
+            "sha-512" => Ok(DigestAlgorithm::Sha512),
+            _ => Err(Error::InvalidInput(format!(
+                "Unsupported digest algorithm {}",
+                alg
+            ))),
+        }
+    }
+}
 
-        if !value_str.is_ascii() {
-            value_str = escape_unicode_chars(&value_str);
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How non-ASCII characters are written in the JSON of a disclosure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnicodeEncoding {
+    /// As `\uXXXX` escapes, with UTF-16 surrogate pairs above U+FFFF, like Python's `json.dumps`.
+    #[default]
+    Escaped,
+    /// As raw UTF-8.
+    Utf8,
+}
+
+impl UnicodeEncoding {
+    fn encode(&self, json: String) -> String {
+        match self {
+            UnicodeEncoding::Utf8 => json,
+            UnicodeEncoding::Escaped if json.is_ascii() => json,
+            UnicodeEncoding::Escaped => escape_unicode_chars(&json),
         }
+    }
+}
+
+/// Whitespace between the tokens of the JSON of a disclosure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsonSpacing {
+    /// A space after `,` and `:`, like Python's `json.dumps` and the examples of the SD-JWT specification.
+    ///
+    /// Nested objects and arrays are spaced as well, unlike in earlier versions of this crate.
+    #[default]
+    Python,
+    /// No whitespace at all.
+    Compact,
+}
+
+impl JsonSpacing {
+    fn serialize(&self, value: &Value) -> String {
+        let mut json = Vec::new();
+        // Writing a JSON value in memory cannot fail.
+        match self {
+            JsonSpacing::Python => {
+                value.serialize(&mut Serializer::with_formatter(&mut json, PythonFormatter))
+            }
+            JsonSpacing::Compact => value.serialize(&mut Serializer::new(&mut json)),
+        }
+        .expect("JSON value is serializable");
+        String::from_utf8(json).expect("JSON is valid UTF-8")
+    }
+}
+
+/// Formatter writing the separators `", "` and `": "` of Python's `json.dumps`.
+struct PythonFormatter;
 
-        let data = if let Some(key) = &key {
-            format!(r#"["{}", {}, {}]"#, salt, escape_json(key), value_str)
+impl Formatter for PythonFormatter {
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if first {
+            Ok(())
         } else {
-            format!(r#"["{}", {}]"#, salt, value_str)
+            writer.write_all(b", ")
+        }
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.begin_array_value(writer, first)
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b": ")
+    }
+}
+
+/// Options of the JSON serialization of disclosures, see [crate::SDJWTIssuer::set_disclosure_format].
+///
+/// The digests only depend on the encoded disclosures, so holders and verifiers accept every format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DisclosureFormat {
+    pub spacing: JsonSpacing,
+    pub unicode: UnicodeEncoding,
+}
+
+/// A disclosure of a selectively disclosable object property `[salt, name, value]` or array element `[salt, value]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disclosure {
+    salt: String,
+    name: Option<String>,
+    value: Value,
+    encoded: String,
+}
+
+impl Disclosure {
+    /// Create a new Disclosure instance with a known salt.
+    ///
+    /// # Arguments
+    /// * `salt` - The salt of the disclosure, it must be random with at least 128 bits of entropy outside of tests.
+    /// * `name` - The claim name of an object property, `None` for an array element.
+    /// * `value` - The value of the claim or array element.
+    ///
+    /// # Errors
+    /// * `InvalidDisclosure` - If the claim name is reserved, i.e. `_sd` or `...`.
+    pub fn new(salt: String, name: Option<String>, value: Value) -> Result<Self> {
+        Self::new_with_format(salt, name, value, DisclosureFormat::default())
+    }
+
+    /// Create a new Disclosure instance with a known salt, serialized with the given format.
+    ///
+    /// # Arguments
+    /// * `salt` - The salt of the disclosure.
+    /// * `name` - The claim name of an object property, `None` for an array element.
+    /// * `value` - The value of the claim or array element.
+    /// * `format` - The JSON serialization of the disclosure, see [DisclosureFormat].
+    ///
+    /// # Errors
+    /// * `InvalidDisclosure` - If the claim name is reserved, i.e. `_sd` or `...`.
+    pub fn new_with_format(
+        salt: String,
+        name: Option<String>,
+        value: Value,
+        format: DisclosureFormat,
+    ) -> Result<Self> {
+        let disclosure = Self::encode(salt, name, value, format);
+        SDJWTCommon::validate_disclosure(
+            &disclosure.digest(DigestAlgorithm::Sha256),
+            &disclosure.to_array(),
+        )?;
+        Ok(disclosure)
+    }
+
+    /// Create a new Disclosure instance with a random salt, as the issuer does.
+    pub(crate) fn with_random_salt(
+        name: Option<String>,
+        value: Value,
+        format: DisclosureFormat,
+    ) -> Self {
+        #[cfg(not(feature = "mock_salts"))]
+        let salt = generate_salt();
+        #[cfg(feature = "mock_salts")]
+        let salt = generate_salt_mock();
+
+        Self::encode(salt, name, value, format)
+    }
+
+    fn encode(salt: String, name: Option<String>, value: Value, format: DisclosureFormat) -> Self {
+        let mut disclosure = Self {
+            salt,
+            name,
+            value,
+            encoded: String::new(),
         };
+        let data = format
+            .unicode
+            .encode(format.spacing.serialize(&disclosure.to_array()));
+        disclosure.encoded = base64url_encode(data.as_bytes());
 
-        let raw_b64 = base64url_encode(data.as_bytes());
-        let hash = base64_hash(raw_b64.as_bytes());
+        disclosure
+    }
 
-        Self {
-            raw_b64,
-            hash,
-        }
+    /// Decodes a disclosure received in an SD-JWT, keeping its encoding as it is.
+    ///
+    /// # Errors
+    /// * `InvalidDisclosure` - If the disclosure is not a base64url-encoded `[salt, name, value]` or `[salt, value]` JSON array.
+    pub fn decode(encoded: &str) -> Result<Self> {
+        let digest = base64_hash(encoded.as_bytes());
+        let decoded = base64url_decode(encoded).map_err(|err| {
+            Error::InvalidDisclosure(format!("Error decoding disclosure {}: {}", encoded, err))
+        })?;
+        let decoded: Value = serde_json::from_slice(&decoded).map_err(|err| {
+            Error::InvalidDisclosure(format!("Error parsing disclosure {}: {}", encoded, err))
+        })?;
+        SDJWTCommon::validate_disclosure(&digest, &decoded)?;
+
+        let mut elements = match decoded {
+            Value::Array(elements) => elements.into_iter(),
+            _ => {
+                return Err(Error::InvalidDisclosure(format!(
+                    "disclosure {}: expected a JSON array",
+                    digest
+                )))
+            }
+        };
+        let salt = elements
+            .next()
+            .and_then(|salt| salt.as_str().map(str::to_owned));
+        let name = match elements.len() {
+            len if len + 1 == OBJECT_DISCLOSURE_LEN => elements
+                .next()
+                .and_then(|name| name.as_str().map(str::to_owned)),
+            len if len + 1 == ARRAY_DISCLOSURE_LEN => None,
+            _ => {
+                return Err(Error::InvalidDisclosure(format!(
+                    "disclosure {}: unexpected length",
+                    digest
+                )))
+            }
+        };
+
+        Ok(Disclosure {
+            salt: salt.unwrap_or_default(),
+            name,
+            value: elements.next().unwrap_or_default(),
+            encoded: encoded.to_owned(),
+        })
+    }
+
+    pub fn salt(&self) -> &str {
+        &self.salt
+    }
+
+    /// The claim name of an object property, `None` for an array element.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The base64url-encoded disclosure, as it appears in an SD-JWT.
+    pub fn encoded(&self) -> &str {
+        &self.encoded
+    }
+
+    /// Computes the digest referencing the disclosure in `_sd` or `{"...": digest}`.
+    pub fn digest(&self, digest_alg: DigestAlgorithm) -> String {
+        digest_alg.digest(self.encoded.as_bytes())
+    }
+
+    fn to_array(&self) -> Value {
+        let mut elements = vec![Value::String(self.salt.clone())];
+        elements.extend(self.name.clone().map(Value::String));
+        elements.push(self.value.clone());
+        Value::Array(elements)
     }
 }
 
+impl fmt::Display for Disclosure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encoded)
+    }
+}
+
+/// Escapes the non-ASCII characters of a JSON text, which can only appear in its strings.
 fn escape_unicode_chars(s: &str) -> String {
-    let mut result = String::new();
+    let mut result = String::with_capacity(s.len());
+    let mut utf16 = [0; 2];
 
     for c in s.chars() {
         if c.is_ascii() {
             result.push(c);
         } else {
-            let esc_c = c.escape_unicode().to_string();
-
-            let esc_c_new = match esc_c.chars().count() {
-                6 => esc_c.replace("\\u{", "\\u00").replace('}', ""), // example: \u{de}
-                7 => esc_c.replace("\\u{", "\\u0").replace('}', ""),  // example: \u{980}
-                8 => esc_c.replace("\\u{", "\\u").replace('}', ""),   // example: \u{23f0}
-                _ => {panic!("unexpected value")}
-            };
-
-            result.push_str(&esc_c_new);
+            for unit in c.encode_utf16(&mut utf16) {
+                let _ = write!(result, "\\u{:04x}", unit);
+            }
         }
     }
 
     result
 }
 
-fn escape_json(s: &str) -> String {
-    Value::String(String::from(s)).to_string()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::utils::base64url_decode;
+    use proptest::prelude::*;
     use regex::Regex;
-
+    use serde_json::json;
 
     #[test]
     fn test_sdjwt_disclosure_when_key_is_none() {
-        let sdjwt_disclosure = SDJWTDisclosure::new(None, "test");
-        let decoded_disclosure: String = String::from_utf8(base64url_decode(&sdjwt_disclosure.raw_b64).unwrap()).unwrap();
+        let sdjwt_disclosure =
+            Disclosure::with_random_salt(None, json!("test"), DisclosureFormat::default());
+        let decoded_disclosure: String =
+            String::from_utf8(base64url_decode(sdjwt_disclosure.encoded()).unwrap()).unwrap();
 
-        let re = Regex::new(r#"\[".*", test]"#).unwrap();
+        let re = Regex::new(r#"\[".*", "test"]"#).unwrap();
         assert!(re.is_match(&decoded_disclosure));
     }
 
     #[test]
     fn test_sdjwt_disclosure_when_key_is_present() {
-        let sdjwt_disclosure = SDJWTDisclosure::new(Some("key".to_string()), "test");
-        let decoded = String::from_utf8(base64url_decode(&sdjwt_disclosure.raw_b64).unwrap()).unwrap();
+        let sdjwt_disclosure = Disclosure::with_random_salt(
+            Some("key".to_string()),
+            json!("test"),
+            DisclosureFormat::default(),
+        );
+        let decoded =
+            String::from_utf8(base64url_decode(sdjwt_disclosure.encoded()).unwrap()).unwrap();
+
+        let re = Regex::new(r#"\[".*", "key", "test"]"#).unwrap();
+        assert!(re.is_match(&decoded));
+    }
+
+    #[test]
+    fn test_disclosure_with_known_salt() {
+        // Example disclosure of the SD-JWT specification
+        let disclosure = Disclosure::new(
+            "_26bc4LT-ac6q2KI6cBW5es".to_string(),
+            Some("family_name".to_string()),
+            json!("Möbius"),
+        )
+        .unwrap();
+        assert_eq!(
+            disclosure.encoded(),
+            "WyJfMjZiYzRMVC1hYzZxMktJNmNCVzVlcyIsICJmYW1pbHlfbmFtZSIsICJNXHUwMGY2Yml1cyJd"
+        );
+        assert_eq!(
+            disclosure.digest(DigestAlgorithm::Sha256),
+            "BwU3T4PB1Wk6TbA1HUOm9XenJYLZfYtJGn8hMl77zwg"
+        );
+        assert_eq!(disclosure.to_string(), disclosure.encoded());
+
+        let element =
+            Disclosure::new("lklxF5jMYlGTPUovMNIvCA".to_string(), None, json!("FR")).unwrap();
+        assert_eq!(
+            element.encoded(),
+            "WyJsa2x4RjVqTVlsR1RQVW92TU5JdkNBIiwgIkZSIl0"
+        );
+        assert_eq!(
+            element.digest(DigestAlgorithm::Sha256),
+            "w0I8EKcdCtUPkGCNUrfwVp2xEgNjtoIDlOxc9-PlOhs"
+        );
+
+        assert!(matches!(
+            Disclosure::new("salt".to_string(), Some("_sd".to_string()), json!([])),
+            Err(Error::InvalidDisclosure(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_disclosure() {
+        // Encoded without spaces, the encoding must be kept to match the digest
+        let encoded = base64url_encode(r#"["salt","address",{"country":"DE"}]"#.as_bytes());
+        let disclosure = Disclosure::decode(&encoded).unwrap();
+        assert_eq!(disclosure.salt(), "salt");
+        assert_eq!(disclosure.name(), Some("address"));
+        assert_eq!(disclosure.value(), &json!({"country": "DE"}));
+        assert_eq!(disclosure.encoded(), encoded);
+        assert_eq!(
+            disclosure.digest(DigestAlgorithm::Sha256),
+            base64_hash(encoded.as_bytes())
+        );
+        assert_eq!(disclosure.digest(DigestAlgorithm::Sha384).len(), 64);
+        assert_eq!(disclosure.digest(DigestAlgorithm::Sha512).len(), 86);
+
+        let element = Disclosure::decode(&base64url_encode(br#"["salt", 42]"#)).unwrap();
+        assert_eq!((element.name(), element.value()), (None, &json!(42)));
+
+        for invalid in [
+            r#"["salt"]"#,
+            r#"{"salt": "name"}"#,
+            r#"[1, "name", "value"]"#,
+            "not json",
+        ] {
+            assert!(matches!(
+                Disclosure::decode(&base64url_encode(invalid.as_bytes())),
+                Err(Error::InvalidDisclosure(_))
+            ));
+        }
+        assert!(matches!(
+            Disclosure::decode("not base64!"),
+            Err(Error::InvalidDisclosure(_))
+        ));
+    }
+
+    #[test]
+    fn test_disclosure_unicode_encoding() {
+        let value = json!("Zoë 😀");
+        let escaped =
+            Disclosure::new("salt".to_string(), Some("名前".to_string()), value.clone()).unwrap();
+        assert_eq!(
+            base64url_decode(escaped.encoded()).unwrap(),
+            br#"["salt", "\u540d\u524d", "Zo\u00eb \ud83d\ude00"]"#
+        );
+
+        let format = DisclosureFormat {
+            unicode: UnicodeEncoding::Utf8,
+            ..Default::default()
+        };
+        let utf8 = Disclosure::new_with_format(
+            "salt".to_string(),
+            Some("名前".to_string()),
+            value.clone(),
+            format,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(base64url_decode(utf8.encoded()).unwrap()).unwrap(),
+            r#"["salt", "名前", "Zoë 😀"]"#
+        );
+
+        for disclosure in [escaped, utf8] {
+            let decoded = Disclosure::decode(disclosure.encoded()).unwrap();
+            assert_eq!((decoded.name(), decoded.value()), (Some("名前"), &value));
+        }
+    }
 
-        let re = Regex::new(r#"\[".*", "key", test]"#).unwrap();
-        assert!(re.is_match(&decoded));    }
+    #[test]
+    fn test_disclosure_json_spacing() {
+        let value =
+            json!({"street": "Sesamstr. 1, 2nd floor", "note": "a:[b], {c}", "floors": [1, 2]});
+        let decoded = |spacing| {
+            let format = DisclosureFormat {
+                spacing,
+                ..Default::default()
+            };
+            let disclosure = Disclosure::new_with_format(
+                "salt".to_string(),
+                Some("address".to_string()),
+                value.clone(),
+                format,
+            )
+            .unwrap();
+            assert_eq!(
+                Disclosure::decode(disclosure.encoded()).unwrap().value(),
+                &value
+            );
+            String::from_utf8(base64url_decode(disclosure.encoded()).unwrap()).unwrap()
+        };
+
+        assert_eq!(
+            decoded(JsonSpacing::Python),
+            r#"["salt", "address", {"street": "Sesamstr. 1, 2nd floor", "note": "a:[b], {c}", "floors": [1, 2]}]"#
+        );
+        assert_eq!(
+            decoded(JsonSpacing::Compact),
+            r#"["salt","address",{"street":"Sesamstr. 1, 2nd floor","note":"a:[b], {c}","floors":[1,2]}]"#
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_disclosure_round_trips_unicode(
+            name in any::<String>().prop_filter("reserved claim name", |name| name != "_sd" && name != "..."),
+            value in any::<String>(),
+            utf8 in any::<bool>(),
+            compact in any::<bool>(),
+        ) {
+            let unicode = if utf8 { UnicodeEncoding::Utf8 } else { UnicodeEncoding::Escaped };
+            let spacing = if compact { JsonSpacing::Compact } else { JsonSpacing::Python };
+            let disclosure = Disclosure::new_with_format(
+                "salt".to_string(),
+                Some(name.clone()),
+                json!([value.clone(), {&name: &value}]),
+                DisclosureFormat { spacing, unicode },
+            ).unwrap();
+            let json = String::from_utf8(base64url_decode(disclosure.encoded()).unwrap()).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), json!(["salt", name, [value, {&name: &value}]]));
+            if utf8 {
+                prop_assert!(json.contains(&serde_json::to_string(&name).unwrap()));
+            } else {
+                prop_assert!(json.is_ascii());
+            }
+
+            let decoded = Disclosure::decode(disclosure.encoded()).unwrap();
+            prop_assert_eq!(decoded, disclosure);
+        }
+    }
+
+    #[test]
+    fn test_digest_algorithm() {
+        for alg in [
+            DigestAlgorithm::Sha256,
+            DigestAlgorithm::Sha384,
+            DigestAlgorithm::Sha512,
+        ] {
+            assert_eq!(alg.to_string().parse::<DigestAlgorithm>().unwrap(), alg);
+        }
+        assert!(matches!(
+            "md5".parse::<DigestAlgorithm>(),
+            Err(Error::InvalidInput(_))
+        ));
+    }
 }
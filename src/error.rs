@@ -21,12 +21,18 @@ pub enum Error {
     #[error("invalid disclosure: {0}")]
     InvalidDisclosure(String),
 
+    #[error("disclosures are not referenced by any digest: {0:?}")]
+    UnreferencedDisclosures(Vec<String>),
+
     #[error("invalid array disclosure: {0}")]
     InvalidArrayDisclosureObject(String),
 
     #[error("invalid path: {0}")]
     InvalidPath(String),
 
+    #[error("claims do not exist: {0:?}")]
+    UnknownClaimPaths(Vec<String>),
+
     #[error("index {idx} is out of bounds for the provided array with length {length}: {msg}")]
     IndexOutOfBounds {
         idx: usize,
@@ -43,6 +49,42 @@ pub enum Error {
     #[error("key not found: {0}")]
     KeyNotFound(String),
 
+    #[error("holder key mismatch: {0}")]
+    HolderKeyMismatch(String),
+
+    #[error("KB-JWT claim mismatch: {0}")]
+    KeyBindingClaimMismatch(String),
+
+    #[error("invalid nonce: {0}")]
+    InvalidNonce(String),
+
+    #[error("KB-JWT was already presented: {0}")]
+    ReplayedKeyBindingJwt(String),
+
+    #[error("claim not disclosed: {0}")]
+    ClaimNotDisclosed(String),
+
+    #[error("required claims are missing: {0:?}")]
+    MissingClaims(Vec<String>),
+
+    #[error("no credential matches: {0:?}")]
+    NoMatchingCredential(Vec<String>),
+
+    #[error("claim has wrong type: {0}")]
+    ClaimTypeMismatch(String),
+
+    #[error("integrity check failed: {0}")]
+    IntegrityError(String),
+
+    #[error("credential pool is exhausted: {0}")]
+    CredentialPoolExhausted(String),
+
+    #[error("credential store error: {0}")]
+    CredentialStoreError(String),
+
+    #[error("schema validation failed: {0}")]
+    SchemaValidationError(String),
+
     #[error("{0}")]
     Unspecified(String),
 }
@@ -7,10 +7,11 @@ use std::vec::Vec;
 use jsonwebtoken::jwk::Jwk;
 use jsonwebtoken::{Algorithm, EncodingKey, Header};
 use rand::Rng;
+use serde::Serialize;
 use serde_json::Value;
 use serde_json::{json, Map as SJMap, Map};
 
-use crate::disclosure::SDJWTDisclosure;
+use crate::disclosure::{Disclosure, DigestAlgorithm, DisclosureFormat};
 use crate::error::Error;
 use crate::utils::{base64_hash, generate_salt};
 use crate::{
@@ -23,7 +24,10 @@ pub struct SDJWTIssuer {
     // parameters
     sign_alg: String,
     add_decoy_claims: bool,
+    disclosure_format: DisclosureFormat,
     extra_header_parameters: Option<HashMap<String, String>>,
+    pub(crate) typ: Option<String>,
+    pub(crate) extra_always_revealed_root_keys: Vec<&'static str>,
 
     // input data
     issuer_key: EncodingKey,
@@ -31,7 +35,7 @@ pub struct SDJWTIssuer {
 
     // internal fields
     inner: SDJWTCommon,
-    all_disclosures: Vec<SDJWTDisclosure>,
+    all_disclosures: Vec<Disclosure>,
     sd_jwt_payload: SJMap<String, Value>,
     signed_sd_jwt: String,
     serialized_sd_jwt: String,
@@ -125,7 +129,10 @@ impl SDJWTIssuer {
         SDJWTIssuer {
             sign_alg: sign_alg.unwrap_or(DEFAULT_SIGNING_ALG.to_owned()),
             add_decoy_claims: false,
+            disclosure_format: DisclosureFormat::default(),
             extra_header_parameters: None,
+            typ: None,
+            extra_always_revealed_root_keys: vec![],
             issuer_key,
             holder_key: None,
             inner: Default::default(),
@@ -144,6 +151,11 @@ impl SDJWTIssuer {
         self.serialized_sd_jwt = Default::default();
     }
 
+    /// Sets the JSON serialization of the disclosures of the SD-JWTs issued afterwards, see [DisclosureFormat].
+    pub fn set_disclosure_format(&mut self, disclosure_format: DisclosureFormat) {
+        self.disclosure_format = disclosure_format;
+    }
+
     /// Issues a SD-JWT.
     ///
     /// # Arguments
@@ -165,6 +177,7 @@ impl SDJWTIssuer {
         // extra_header_parameters: Option<HashMap<String, String>>,
     ) -> Result<String> {
         let inner = SDJWTCommon {
+            typ: self.typ.clone(),
             serialization_format,
             ..Default::default()
         };
@@ -185,6 +198,29 @@ impl SDJWTIssuer {
         Ok(self.serialized_sd_jwt.clone())
     }
 
+    /// Issues a SD-JWT from a serializable value, e.g. a struct with the claims.
+    ///
+    /// The arguments are the same as for [SDJWTIssuer::issue_sd_jwt], with `user_claims`
+    /// serialized to a JSON object first.
+    pub fn issue_sd_jwt_typed<T: Serialize>(
+        &mut self,
+        user_claims: &T,
+        sd_strategy: ClaimsForSelectiveDisclosureStrategy,
+        holder_key: Option<Jwk>,
+        add_decoy_claims: bool,
+        serialization_format: SDJWTSerializationFormat,
+    ) -> Result<String> {
+        let user_claims = serde_json::to_value(user_claims)
+            .map_err(|e| Error::ConversionError(format!("json value: {}", e)))?;
+        self.issue_sd_jwt(
+            user_claims,
+            sd_strategy,
+            holder_key,
+            add_decoy_claims,
+            serialization_format,
+        )
+    }
+
     fn assemble_sd_jwt_payload(
         &mut self,
         mut user_claims: Value,
@@ -196,6 +232,7 @@ impl SDJWTIssuer {
         let always_revealed_root_keys = vec!["iss", "iat", "exp"];
         let mut always_revealed_claims: Map<String, Value> = always_revealed_root_keys
             .into_iter()
+            .chain(self.extra_always_revealed_root_keys.iter().copied())
             .filter_map(|key| claims_obj_ref.shift_remove_entry(key))
             .collect();
 
@@ -236,8 +273,8 @@ impl SDJWTIssuer {
             let subtree = self.create_sd_claims(object, strategy_for_child);
 
             if sd_strategy.sd_for_key(&key) {
-                let disclosure = SDJWTDisclosure::new(None, subtree);
-                claims.push(json!({ SD_LIST_PREFIX: disclosure.hash}));
+                let disclosure = Disclosure::with_random_salt(None, subtree, self.disclosure_format);
+                claims.push(json!({ SD_LIST_PREFIX: disclosure.digest(DigestAlgorithm::Sha256)}));
                 self.all_disclosures.push(disclosure);
             } else {
                 claims.push(subtree);
@@ -263,8 +300,8 @@ impl SDJWTIssuer {
             let subtree_from_here = self.create_sd_claims(value, strategy_for_child);
 
             if sd_strategy.sd_for_key(key) {
-                let disclosure = SDJWTDisclosure::new(Some(key.to_owned()), subtree_from_here);
-                sd_claims.push(disclosure.hash.clone());
+                let disclosure = Disclosure::with_random_salt(Some(key.to_owned()), subtree_from_here, self.disclosure_format);
+                sd_claims.push(disclosure.digest(DigestAlgorithm::Sha256));
                 self.all_disclosures.push(disclosure);
             } else {
                 claims.insert(key.to_owned(), subtree_from_here);
@@ -317,7 +354,7 @@ impl SDJWTIssuer {
             let mut disclosures: VecDeque<String> = self
                 .all_disclosures
                 .iter()
-                .map(|d| d.raw_b64.to_string())
+                .map(|d| d.encoded().to_string())
                 .collect();
             disclosures.push_front(self.signed_sd_jwt.clone());
 
@@ -344,7 +381,7 @@ impl SDJWTIssuer {
                 disclosures: self
                     .all_disclosures
                     .iter()
-                    .map(|d| d.raw_b64.to_string())
+                    .map(|d| d.encoded().to_string())
                     .collect(),
             };
             self.serialized_sd_jwt = serde_json::to_string(&sd_jwt_json)
@@ -8,10 +8,22 @@ use strum::Display;
 use std::collections::HashMap;
 pub use {holder::SDJWTHolder, issuer::SDJWTIssuer, issuer::ClaimsForSelectiveDisclosureStrategy, verifier::SDJWTVerifier};
 
-mod disclosure;
+pub mod claim_path;
+pub mod credential_pool;
+pub mod credential_store;
+pub mod dcql;
+pub mod disclosure;
 pub mod error;
 pub mod holder;
 pub mod issuer;
+pub mod nonce;
+pub mod policy;
+pub mod presentation_exchange;
+pub mod presentation_set;
+pub mod sd_jwt;
+pub mod sd_jwt_vc;
+pub mod signer;
+pub mod status_list;
 pub mod utils;
 pub mod verifier;
 
@@ -23,18 +35,28 @@ const SD_LIST_PREFIX: &str = "...";
 const _SD_JWT_TYP_HEADER: &str = "sd+jwt";
 const KB_JWT_TYP_HEADER: &str = "kb+jwt";
 const KB_DIGEST_KEY: &str = "sd_hash";
+const KB_JWT_RESERVED_HEADERS: [&str; 2] = ["alg", "typ"];
+const KB_JWT_RESERVED_CLAIMS: [&str; 4] = ["nonce", "aud", "iat", KB_DIGEST_KEY];
+const TRANSACTION_DATA_HASHES_KEY: &str = "transaction_data_hashes";
+const TRANSACTION_DATA_HASHES_ALG_KEY: &str = "transaction_data_hashes_alg";
 pub const COMBINED_SERIALIZATION_FORMAT_SEPARATOR: &str = "~";
 const JWT_SEPARATOR: &str = ".";
 const CNF_KEY: &str = "cnf";
 const JWK_KEY: &str = "jwk";
+const OBJECT_DISCLOSURE_LEN: usize = 3;
+const ARRAY_DISCLOSURE_LEN: usize = 2;
+const OBJECT_DISCLOSURE_SHAPE: &str = "[salt, claim_name, claim_value] for an object property";
+const ARRAY_DISCLOSURE_SHAPE: &str = "[salt, value] for an array element";
+const RESERVED_CLAIM_NAMES: [&str; 2] = [SD_DIGESTS_KEY, SD_LIST_PREFIX];
 
+#[allow(dead_code)]
 #[derive(Debug)]
 pub(crate) struct SDJWTHasSDClaimException(String);
 
 impl SDJWTHasSDClaimException {}
 
 /// SDJWTSerializationFormat is used to determine how an SD-JWT is serialized to String
-#[derive(Default, Clone, PartialEq, Debug, Display)]
+#[derive(Default, Clone, PartialEq, Debug, Display, Serialize, Deserialize)]
 pub enum SDJWTSerializationFormat {
     /// JSON-encoded representation
     #[default]
@@ -43,6 +65,86 @@ pub enum SDJWTSerializationFormat {
     Compact,
 }
 
+impl SDJWTSerializationFormat {
+    /// Detects the serialization format of an SD-JWT or a presentation.
+    ///
+    /// # Errors
+    /// * `InvalidInput` - If the input is neither a JSON object nor a `~` separated SD-JWT.
+    pub fn detect(sd_jwt: &str) -> Result<Self> {
+        let sd_jwt = sd_jwt.trim_start();
+        if sd_jwt.starts_with('{') {
+            Ok(SDJWTSerializationFormat::JSON)
+        } else if sd_jwt.contains(COMBINED_SERIALIZATION_FORMAT_SEPARATOR) {
+            Ok(SDJWTSerializationFormat::Compact)
+        } else {
+            Err(Error::InvalidInput(
+                "Cannot detect the serialization format of the SD-JWT".to_string(),
+            ))
+        }
+    }
+
+    /// Checks that an SD-JWT is in this serialization format, if its format can be detected.
+    pub(crate) fn check(&self, sd_jwt: &str) -> Result<()> {
+        match Self::detect(sd_jwt) {
+            Ok(detected) if &detected != self => Err(Error::InvalidInput(format!(
+                "SD-JWT is in {} serialization, not in {}",
+                detected, self
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Converts an SD-JWT or a presentation to another serialization format.
+///
+/// The issuer-signed JWT, the disclosures and the KB-JWT are kept as they are, so the signatures and digests remain valid.
+///
+/// # Arguments
+/// * `sd_jwt` - SD-JWT or presentation in any serialization format.
+/// * `target` - The serialization format to convert to.
+///
+/// # Returns
+/// * `String` - The SD-JWT or presentation in the `target` serialization format.
+///
+/// # Errors
+/// * `InvalidInput` - If the serialization format cannot be detected or the SD-JWT is malformed.
+/// * `DeserializationError` - If the JSON serialization is not valid.
+pub fn convert_serialization(sd_jwt: &str, target: SDJWTSerializationFormat) -> Result<String> {
+    let source = SDJWTSerializationFormat::detect(sd_jwt)?;
+    if source == target {
+        return Ok(sd_jwt.to_owned());
+    }
+
+    match target {
+        SDJWTSerializationFormat::JSON => {
+            let mut parts: Vec<&str> = sd_jwt.trim().split(COMBINED_SERIALIZATION_FORMAT_SEPARATOR).collect();
+            let kb_jwt = parts.pop().filter(|kb_jwt| !kb_jwt.is_empty()).map(str::to_owned);
+            let jwt: Vec<&str> = parts[0].split(JWT_SEPARATOR).collect();
+            if jwt.len() != 3 {
+                return Err(Error::InvalidInput(format!("Invalid JWT in SD-JWT: {}", parts[0])));
+            }
+            let sd_jwt_json = SDJWTJson {
+                protected: jwt[0].to_owned(),
+                payload: jwt[1].to_owned(),
+                signature: jwt[2].to_owned(),
+                disclosures: parts[1..].iter().map(|disclosure| disclosure.to_string()).collect(),
+                kb_jwt,
+            };
+            serde_json::to_string(&sd_jwt_json).map_err(|e| Error::DeserializationError(e.to_string()))
+        }
+        SDJWTSerializationFormat::Compact => {
+            let sd_jwt_json: SDJWTJson =
+                serde_json::from_str(sd_jwt).map_err(|e| Error::DeserializationError(e.to_string()))?;
+            let jwt = [sd_jwt_json.protected, sd_jwt_json.payload, sd_jwt_json.signature].join(JWT_SEPARATOR);
+            let mut parts = Vec::with_capacity(sd_jwt_json.disclosures.len() + 2);
+            parts.push(jwt);
+            parts.extend(sd_jwt_json.disclosures);
+            parts.push(sd_jwt_json.kb_jwt.unwrap_or_default());
+            Ok(parts.join(COMBINED_SERIALIZATION_FORMAT_SEPARATOR))
+        }
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct SDJWTCommon {
     typ: Option<String>,
@@ -87,6 +189,7 @@ impl SDJWTCommon {
                 })?;
 
             let hash = base64_hash(disclosure.as_bytes());
+            Self::validate_disclosure(&hash, &decoded_disclosure)?;
             if self.hash_to_decoded_disclosure.contains_key(&hash) {
                 return Err(Error::DuplicateDigestError(hash));
             }
@@ -99,6 +202,95 @@ impl SDJWTCommon {
         Ok(())
     }
 
+    /// Checks the structure of a decoded disclosure, which must be either
+    /// `[salt, claim_name, claim_value]` or `[salt, array_element]`.
+    fn validate_disclosure(digest: &str, disclosure: &Value) -> Result<()> {
+        let elements = disclosure.as_array().ok_or_else(|| {
+            Error::InvalidDisclosure(format!(
+                "disclosure {}: expected a JSON array, got {}",
+                digest, disclosure
+            ))
+        })?;
+        match elements.len() {
+            OBJECT_DISCLOSURE_LEN | ARRAY_DISCLOSURE_LEN => {}
+            len => {
+                return Err(Error::InvalidDisclosure(format!(
+                    "disclosure {}: expected {} or {}, got an array with {} elements",
+                    digest, OBJECT_DISCLOSURE_SHAPE, ARRAY_DISCLOSURE_SHAPE, len
+                )));
+            }
+        }
+        if !elements[0].is_string() {
+            return Err(Error::InvalidDisclosure(format!(
+                "disclosure {}: expected the salt to be a string, got {}",
+                digest, elements[0]
+            )));
+        }
+        if elements.len() == OBJECT_DISCLOSURE_LEN {
+            match elements[1].as_str() {
+                Some(claim_name) if RESERVED_CLAIM_NAMES.contains(&claim_name) => {
+                    return Err(Error::InvalidDisclosure(format!(
+                        "disclosure {}: claim name `{}` is reserved",
+                        digest, claim_name
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    return Err(Error::InvalidDisclosure(format!(
+                        "disclosure {}: expected the claim name to be a string, got {}",
+                        digest, elements[1]
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the claim name and value of the disclosure referenced from an `_sd` array.
+    /// `None` is returned for unknown digests (i.e. decoys).
+    fn object_disclosure(&self, digest: &str) -> Result<Option<(&str, &Value)>> {
+        match self.decoded_disclosure_with_len(digest, OBJECT_DISCLOSURE_LEN, OBJECT_DISCLOSURE_SHAPE)? {
+            Some(disclosure) => {
+                let claim_name = disclosure[1].as_str().ok_or_else(|| {
+                    Error::InvalidDisclosure(format!(
+                        "disclosure {}: expected the claim name to be a string",
+                        digest
+                    ))
+                })?;
+                Ok(Some((claim_name, &disclosure[2])))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the value of the disclosure referenced from an array element `{"...": digest}`.
+    /// `None` is returned for unknown digests (i.e. decoys).
+    fn array_disclosure(&self, digest: &str) -> Result<Option<&Value>> {
+        Ok(self
+            .decoded_disclosure_with_len(digest, ARRAY_DISCLOSURE_LEN, ARRAY_DISCLOSURE_SHAPE)?
+            .map(|disclosure| &disclosure[1]))
+    }
+
+    fn decoded_disclosure_with_len(
+        &self,
+        digest: &str,
+        expected_len: usize,
+        expected_shape: &str,
+    ) -> Result<Option<&Vec<Value>>> {
+        let disclosure = match self.hash_to_decoded_disclosure.get(digest) {
+            Some(disclosure) => disclosure,
+            None => return Ok(None),
+        };
+        match disclosure.as_array() {
+            Some(elements) if elements.len() == expected_len => Ok(Some(elements)),
+            _ => Err(Error::InvalidDisclosure(format!(
+                "disclosure {}: expected {}, got {}",
+                digest, expected_shape, disclosure
+            ))),
+        }
+    }
+
     fn check_for_sd_claim(the_object: &Value) -> Result<()> {
         match the_object {
             Value::Object(obj) => {
@@ -189,6 +381,7 @@ impl SDJWTCommon {
     }
 
     fn parse_sd_jwt(&mut self, sd_jwt_with_disclosures: String) -> Result<()> {
+        self.serialization_format.check(&sd_jwt_with_disclosures)?;
         match self.serialization_format {
             SDJWTSerializationFormat::Compact => {
                 self.parse_compact_sd_jwt(sd_jwt_with_disclosures)
@@ -202,7 +395,31 @@ impl SDJWTCommon {
 
 #[cfg(test)]
 mod tests {
-    use crate::{utils, SDJWTCommon};
+    use crate::error::Error;
+    use crate::utils::base64_hash;
+    use crate::{convert_serialization, utils, SDJWTCommon, SDJWTSerializationFormat};
+
+    fn common_with_disclosures(disclosures: &[&str]) -> SDJWTCommon {
+        SDJWTCommon {
+            input_disclosures: disclosures
+                .iter()
+                .map(|disclosure| utils::base64url_encode(disclosure.as_bytes()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn assert_invalid_disclosure(disclosure: &str, expected_msg: &str) {
+        let mut sdjwt = common_with_disclosures(&[disclosure]);
+        match sdjwt.create_hash_mappings() {
+            Err(Error::InvalidDisclosure(msg)) => {
+                let digest = base64_hash(sdjwt.input_disclosures[0].as_bytes());
+                assert!(msg.contains(&digest), "{}", msg);
+                assert!(msg.contains(expected_msg), "{}", msg);
+            }
+            other => panic!("disclosure {} must be rejected, got {:?}", disclosure, other),
+        }
+    }
 
 
     #[test]
@@ -226,4 +443,72 @@ mod tests {
         assert_eq!(sdjwt.unverified_input_key_binding_jwt.unwrap(), "kbjwt");
         assert_eq!(sdjwt.input_disclosures, vec!["disc1".to_string(), "disc2".to_string()]);
     }
+
+    #[test]
+    fn test_detect_and_convert_serialization() {
+        let compact = "h.p.s~disc1~disc2~kbjwt";
+        let json = r#"{"protected":"h","payload":"p","signature":"s","disclosures":["disc1","disc2"],"kb_jwt":"kbjwt"}"#;
+        assert_eq!(SDJWTSerializationFormat::detect(compact).unwrap(), SDJWTSerializationFormat::Compact);
+        assert_eq!(SDJWTSerializationFormat::detect(&format!(" {}", json)).unwrap(), SDJWTSerializationFormat::JSON);
+        assert!(matches!(SDJWTSerializationFormat::detect("h.p.s"), Err(Error::InvalidInput(_))));
+
+        assert_eq!(convert_serialization(compact, SDJWTSerializationFormat::JSON).unwrap(), json);
+        assert_eq!(convert_serialization(json, SDJWTSerializationFormat::Compact).unwrap(), compact);
+        assert_eq!(convert_serialization(compact, SDJWTSerializationFormat::Compact).unwrap(), compact);
+        let without_kb = convert_serialization("h.p.s~", SDJWTSerializationFormat::JSON).unwrap();
+        assert_eq!(without_kb, r#"{"protected":"h","payload":"p","signature":"s","disclosures":[],"kb_jwt":null}"#);
+        assert_eq!(convert_serialization(&without_kb, SDJWTSerializationFormat::Compact).unwrap(), "h.p.s~");
+        assert!(matches!(convert_serialization("h.p~kbjwt", SDJWTSerializationFormat::JSON), Err(Error::InvalidInput(_))));
+
+        let mut sdjwt = SDJWTCommon {
+            serialization_format: SDJWTSerializationFormat::Compact,
+            ..Default::default()
+        };
+        match sdjwt.parse_sd_jwt(json.to_string()) {
+            Err(Error::InvalidInput(msg)) => assert!(msg.contains("JSON"), "{}", msg),
+            other => panic!("JSON serialization must not be parsed as compact, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_create_hash_mappings_rejects_malformed_disclosures() {
+        assert_invalid_disclosure(r#"{"salt": "name"}"#, "expected a JSON array");
+        assert_invalid_disclosure(r#"["salt"]"#, "got an array with 1 elements");
+        assert_invalid_disclosure(r#"["salt", "name", "value", "extra"]"#, "got an array with 4 elements");
+        assert_invalid_disclosure(r#"[1, "name", "value"]"#, "expected the salt to be a string");
+        assert_invalid_disclosure(r#"["salt", 1, "value"]"#, "expected the claim name to be a string");
+        assert_invalid_disclosure(r#"["salt", "_sd", "value"]"#, "claim name `_sd` is reserved");
+        assert_invalid_disclosure(r#"["salt", "...", "value"]"#, "claim name `...` is reserved");
+    }
+
+    #[test]
+    fn test_disclosure_shape_depends_on_context() {
+        let mut sdjwt = common_with_disclosures(&[
+            r#"["salt", "name", "value"]"#,
+            r#"["salt", "element"]"#,
+        ]);
+        sdjwt.create_hash_mappings().unwrap();
+        let object_digest = base64_hash(sdjwt.input_disclosures[0].as_bytes());
+        let array_digest = base64_hash(sdjwt.input_disclosures[1].as_bytes());
+
+        let (name, value) = sdjwt.object_disclosure(&object_digest).unwrap().unwrap();
+        assert_eq!((name, value.as_str()), ("name", Some("value")));
+        assert_eq!(sdjwt.array_disclosure(&array_digest).unwrap().unwrap(), "element");
+        assert!(sdjwt.object_disclosure("decoy").unwrap().is_none());
+
+        match sdjwt.object_disclosure(&array_digest) {
+            Err(Error::InvalidDisclosure(msg)) => {
+                assert!(msg.contains(&array_digest));
+                assert!(msg.contains("[salt, claim_name, claim_value]"));
+            }
+            other => panic!("array disclosure must not be accepted for an object property, got {:?}", other),
+        }
+        match sdjwt.array_disclosure(&object_digest) {
+            Err(Error::InvalidDisclosure(msg)) => {
+                assert!(msg.contains(&object_digest));
+                assert!(msg.contains("[salt, value]"));
+            }
+            other => panic!("object disclosure must not be accepted for an array element, got {:?}", other),
+        }
+    }
 }
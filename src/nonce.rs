@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use rand::prelude::ThreadRng;
+use rand::RngCore;
+
+use crate::error::{Error, Result};
+use crate::utils::{base64url_encode, current_timestamp};
+
+const NONCE_LEN: usize = 32;
+
+/// NonceManager issues the nonces a verifier expects in KB-JWTs and accepts each of them once.
+pub trait NonceManager {
+    /// Issues a new nonce to be sent to a holder.
+    fn issue_nonce(&self) -> Result<String>;
+
+    /// Consumes a nonce received in a KB-JWT.
+    ///
+    /// # Errors
+    /// * `InvalidNonce` - If the nonce was not issued, has expired or was already consumed.
+    fn consume_nonce(&self, nonce: &str) -> Result<()>;
+}
+
+/// ReplayCache remembers the KB-JWTs a verifier has accepted.
+pub trait ReplayCache {
+    /// Records a KB-JWT by its hash.
+    ///
+    /// # Errors
+    /// * `ReplayedKeyBindingJwt` - If a KB-JWT with the same hash was already recorded.
+    fn check_and_insert(&self, kb_jwt_hash: &str) -> Result<()>;
+}
+
+/// In-memory [NonceManager], nonces are valid for `ttl` seconds and can be consumed once.
+pub struct InMemoryNonceManager {
+    ttl: u64,
+    nonces: Mutex<HashMap<String, u64>>,
+}
+
+impl InMemoryNonceManager {
+    /// Create a new InMemoryNonceManager instance.
+    ///
+    /// # Arguments
+    /// * `ttl` - Number of seconds an issued nonce is valid for.
+    pub fn new(ttl: u64) -> Self {
+        InMemoryNonceManager {
+            ttl,
+            nonces: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl NonceManager for InMemoryNonceManager {
+    fn issue_nonce(&self) -> Result<String> {
+        let mut buf = [0u8; NONCE_LEN];
+        ThreadRng::default().fill_bytes(&mut buf);
+        let nonce = base64url_encode(&buf);
+
+        let now = current_timestamp()?;
+        let mut nonces = lock(&self.nonces)?;
+        nonces.retain(|_, expires_at| *expires_at > now);
+        nonces.insert(nonce.clone(), now + self.ttl);
+
+        Ok(nonce)
+    }
+
+    fn consume_nonce(&self, nonce: &str) -> Result<()> {
+        let now = current_timestamp()?;
+        match lock(&self.nonces)?.remove(nonce) {
+            Some(expires_at) if expires_at > now => Ok(()),
+            Some(_) => Err(Error::InvalidNonce(format!("nonce {} has expired", nonce))),
+            None => Err(Error::InvalidNonce(format!(
+                "nonce {} was not issued or was already used",
+                nonce
+            ))),
+        }
+    }
+}
+
+/// In-memory [ReplayCache], KB-JWT hashes are remembered for `ttl` seconds.
+///
+/// The `ttl` should not be shorter than the period during which the verifier accepts a KB-JWT,
+/// i.e. the lifetime of its nonces or the accepted age of its `iat`.
+pub struct InMemoryReplayCache {
+    ttl: u64,
+    seen: Mutex<HashMap<String, u64>>,
+}
+
+impl InMemoryReplayCache {
+    /// Create a new InMemoryReplayCache instance.
+    ///
+    /// # Arguments
+    /// * `ttl` - Number of seconds a KB-JWT hash is remembered for.
+    pub fn new(ttl: u64) -> Self {
+        InMemoryReplayCache {
+            ttl,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl ReplayCache for InMemoryReplayCache {
+    fn check_and_insert(&self, kb_jwt_hash: &str) -> Result<()> {
+        let now = current_timestamp()?;
+        let mut seen = lock(&self.seen)?;
+        seen.retain(|_, expires_at| *expires_at > now);
+        if seen.contains_key(kb_jwt_hash) {
+            return Err(Error::ReplayedKeyBindingJwt(kb_jwt_hash.to_string()));
+        }
+        seen.insert(kb_jwt_hash.to_string(), now + self.ttl);
+
+        Ok(())
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> Result<std::sync::MutexGuard<'_, T>> {
+    mutex
+        .lock()
+        .map_err(|e| Error::InvalidState(format!("lock is poisoned: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::nonce::{InMemoryNonceManager, InMemoryReplayCache, NonceManager, ReplayCache};
+
+    #[test]
+    fn test_nonce_is_single_use() {
+        let nonce_manager = InMemoryNonceManager::new(300);
+        let nonce = nonce_manager.issue_nonce().unwrap();
+        assert_ne!(nonce, nonce_manager.issue_nonce().unwrap());
+
+        nonce_manager.consume_nonce(&nonce).unwrap();
+        assert!(matches!(nonce_manager.consume_nonce(&nonce), Err(Error::InvalidNonce(_))));
+        assert!(matches!(nonce_manager.consume_nonce("unknown"), Err(Error::InvalidNonce(_))));
+    }
+
+    #[test]
+    fn test_nonce_expires() {
+        let nonce_manager = InMemoryNonceManager::new(0);
+        let nonce = nonce_manager.issue_nonce().unwrap();
+
+        match nonce_manager.consume_nonce(&nonce) {
+            Err(Error::InvalidNonce(msg)) => assert!(msg.contains("expired")),
+            _ => panic!("expired nonce must be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_replay_cache() {
+        let replay_cache = InMemoryReplayCache::new(300);
+        replay_cache.check_and_insert("hash").unwrap();
+        replay_cache.check_and_insert("other hash").unwrap();
+
+        match replay_cache.check_and_insert("hash") {
+            Err(Error::ReplayedKeyBindingJwt(hash)) => assert_eq!(hash, "hash"),
+            _ => panic!("replayed KB-JWT must be rejected"),
+        }
+    }
+}
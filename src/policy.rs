@@ -0,0 +1,162 @@
+use serde_json::Value;
+
+use crate::claim_path::ClaimPath;
+use crate::error::{Error, Result};
+use crate::verifier::VerificationReport;
+
+/// ClaimsPolicy describes which claims a relying party expects in a presentation.
+#[derive(Clone, Debug, Default)]
+pub struct ClaimsPolicy {
+    required: Vec<ClaimPath>,
+    optional: Vec<ClaimPath>,
+    strip_unrequested: bool,
+}
+
+impl ClaimsPolicy {
+    /// Create a new ClaimsPolicy instance.
+    ///
+    /// # Arguments
+    /// * `required` - JSONPaths of the claims which must be part of the verified claims, e.g. `$.address.country`.
+    /// * `optional` - JSONPaths of the claims which may be part of the verified claims.
+    /// * `strip_unrequested` - If true, selectively disclosed claims which are neither required nor optional are removed from the verified claims.
+    ///
+    /// # Returns
+    /// * `ClaimsPolicy` - The ClaimsPolicy instance.
+    ///
+    /// # Errors
+    /// * `InvalidPath` - If one of the paths is not a valid JSONPath.
+    pub fn new(required: &[&str], optional: &[&str], strip_unrequested: bool) -> Result<Self> {
+        Ok(ClaimsPolicy {
+            required: Self::parse_paths(required)?,
+            optional: Self::parse_paths(optional)?,
+            strip_unrequested,
+        })
+    }
+
+    /// Applies the policy to verified claims.
+    ///
+    /// # Arguments
+    /// * `verified_claims` - The verified claims of a presentation.
+    /// * `report` - The report of the verification of the same presentation, used to find the selectively disclosed claims.
+    ///
+    /// # Returns
+    /// * `Value` - The verified claims, without unrequested selectively disclosed claims if `strip_unrequested` is set.
+    ///   Claims which are always visible in the issuer-signed JWT are never removed.
+    ///
+    /// # Errors
+    /// * `MissingClaims` - If required claims are not part of the verified claims, with the JSONPaths of all of them.
+    pub fn apply(&self, verified_claims: &Value, report: &VerificationReport) -> Result<Value> {
+        let missing: Vec<String> = self
+            .required
+            .iter()
+            .filter(|path| path.get(verified_claims).is_none())
+            .map(ToString::to_string)
+            .collect();
+        if !missing.is_empty() {
+            return Err(Error::MissingClaims(missing));
+        }
+
+        let mut claims = verified_claims.clone();
+        if self.strip_unrequested {
+            let mut unrequested = Vec::new();
+            for disclosed_claim in &report.disclosed_claims {
+                let path: ClaimPath = disclosed_claim.path.parse()?;
+                if !self.is_requested(&path) {
+                    unrequested.push(path);
+                }
+            }
+            // Removing deeper paths and higher array indices first keeps the remaining paths valid.
+            unrequested.sort();
+            for path in unrequested.iter().rev() {
+                path.remove(&mut claims);
+            }
+        }
+
+        Ok(claims)
+    }
+
+    /// A claim is requested if it is, contains or is contained in a required or optional claim.
+    fn is_requested(&self, path: &ClaimPath) -> bool {
+        self.required
+            .iter()
+            .chain(self.optional.iter())
+            .any(|requested| requested.starts_with(path) || path.starts_with(requested))
+    }
+
+    fn parse_paths(paths: &[&str]) -> Result<Vec<ClaimPath>> {
+        paths.iter().map(|path| path.parse()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::error::Error;
+    use crate::policy::ClaimsPolicy;
+    use crate::verifier::{DisclosedClaim, VerificationReport};
+
+    fn report(disclosed_paths: &[&str]) -> VerificationReport {
+        VerificationReport {
+            disclosed_claims: disclosed_paths
+                .iter()
+                .map(|path| DisclosedClaim {
+                    path: path.to_string(),
+                    digest: format!("digest of {}", path),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_missing_required_claims() {
+        let claims = json!({"iss": "https://example.com", "given_name": "John", "address": {"locality": "Berlin"}});
+        let policy = ClaimsPolicy::new(&["$.given_name", "$.birthdate", "$.address.country"], &[], false).unwrap();
+
+        match policy.apply(&claims, &report(&["$.given_name"])) {
+            Err(Error::MissingClaims(paths)) => assert_eq!(paths, vec!["$.birthdate", "$.address.country"]),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_strip_unrequested_claims() {
+        let claims = json!({
+            "iss": "https://example.com",
+            "given_name": "John",
+            "family_name": "Doe",
+            "birthdate": "1940-01-01",
+            "nationalities": ["US", "DE", "FR"],
+            "address": {"locality": "Berlin", "country": "DE"}
+        });
+        let report = report(&[
+            "$.given_name",
+            "$.family_name",
+            "$.birthdate",
+            "$.nationalities[0]",
+            "$.nationalities[2]",
+            "$.address",
+            "$.address.country",
+        ]);
+
+        let policy = ClaimsPolicy::new(&["$.given_name", "$.address.country"], &["$.birthdate"], false).unwrap();
+        assert_eq!(policy.apply(&claims, &report).unwrap(), claims);
+
+        let policy = ClaimsPolicy::new(&["$.given_name", "$.address.country"], &["$.nationalities[2]"], true).unwrap();
+        assert_eq!(
+            policy.apply(&claims, &report).unwrap(),
+            json!({
+                "iss": "https://example.com",
+                "given_name": "John",
+                "nationalities": ["DE", "FR"],
+                "address": {"locality": "Berlin", "country": "DE"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_path() {
+        assert!(matches!(ClaimsPolicy::new(&["given_name"], &[], false), Err(Error::InvalidPath(_))));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::claim_path::{ClaimPath, PathSegment};
 use crate::error;
 use crate::error::Error;
 use crate::error::Error::DeserializationError;
@@ -9,7 +10,10 @@ use error::Result;
 use lazy_static::lazy_static;
 use rand::prelude::ThreadRng;
 use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde_json::error::Category;
 use serde_json::Value;
+use serde_path_to_error::Segment;
 use sha2::Digest;
 #[cfg(feature = "mock_salts")]
 use std::{collections::VecDeque, sync::Mutex};
@@ -60,3 +64,115 @@ pub(crate) fn jwt_payload_decode(b64data: &str) -> Result<serde_json::Map<String
     )
         .map_err(|e| DeserializationError(e.to_string()))
 }
+
+pub(crate) fn current_timestamp() -> Result<u64> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| Error::ConversionError(format!("timestamp: {}", e)))?
+        .as_secs())
+}
+
+/// Deserializes claims into `T`, telling apart missing (i.e. not disclosed) claims from claims of the wrong type.
+pub(crate) fn deserialize_claims<T: DeserializeOwned>(claims: &Value) -> Result<T> {
+    serde_path_to_error::deserialize(claims).map_err(|e| {
+        let path = ClaimPath::from_segments(
+            e.path()
+                .iter()
+                .map_while(|segment| match segment {
+                    Segment::Seq { index } => Some(PathSegment::Index(*index)),
+                    Segment::Map { key } => Some(PathSegment::Key(key.to_owned())),
+                    Segment::Enum { variant } => Some(PathSegment::Key(variant.to_owned())),
+                    Segment::Unknown => None,
+                })
+                .collect(),
+        );
+        let error = e.into_inner();
+        // The path of a missing field is the path of its object, serde only names the field in the message.
+        let missing_field = match error.classify() {
+            Category::Data => error
+                .to_string()
+                .strip_prefix("missing field `")
+                .and_then(|rest| rest.strip_suffix('`'))
+                .map(str::to_owned),
+            _ => None,
+        };
+        match missing_field {
+            Some(field) => Error::ClaimNotDisclosed(path.key(&field).to_string()),
+            None => Error::ClaimTypeMismatch(format!("{}: {}", path, error)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::error::Error;
+    use crate::utils::deserialize_claims;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    enum Gender {
+        Female,
+        Other(String),
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct PlaceOfBirth {
+        locality: String,
+        country: String,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        given_name: String,
+        middle_name: Option<String>,
+        gender: Gender,
+        nationalities: Vec<String>,
+        place_of_birth: PlaceOfBirth,
+        age_birth_year: u16,
+    }
+
+    #[test]
+    fn test_deserialize_claims() {
+        let mut claims = json!({
+            "given_name": "Erika",
+            "middle_name": null,
+            "gender": {"other": "n/a"},
+            "nationalities": ["DE", "US"],
+            "place_of_birth": {"locality": "Berlin", "country": "DE"},
+            "age_birth_year": 1963
+        });
+        let person: Person = deserialize_claims(&claims).unwrap();
+        assert_eq!(person.gender, Gender::Other("n/a".to_string()));
+        assert_eq!((person.middle_name, person.place_of_birth.locality, person.age_birth_year), (None, "Berlin".to_string(), 1963));
+
+        claims["age_birth_year"] = json!("1963");
+        match deserialize_claims::<Person>(&claims) {
+            Err(Error::ClaimTypeMismatch(msg)) => assert!(msg.starts_with("$.age_birth_year: "), "{}", msg),
+            other => panic!("expected a type mismatch, got {:?}", other),
+        }
+
+        claims["age_birth_year"] = json!(1963);
+        claims["place_of_birth"] = json!({"country": "DE"});
+        match deserialize_claims::<Person>(&claims) {
+            Err(Error::ClaimNotDisclosed(path)) => assert_eq!(path, "$.place_of_birth.locality"),
+            other => panic!("expected an undisclosed claim, got {:?}", other),
+        }
+
+        claims["place_of_birth"]["locality"] = json!("Berlin");
+        claims["nationalities"][1] = json!(840);
+        match deserialize_claims::<Person>(&claims) {
+            Err(Error::ClaimTypeMismatch(msg)) => assert!(msg.starts_with("$.nationalities[1]: "), "{}", msg),
+            other => panic!("expected a type mismatch, got {:?}", other),
+        }
+
+        claims["nationalities"][1] = json!("US");
+        claims["gender"] = json!("unknown");
+        match deserialize_claims::<Person>(&claims) {
+            Err(Error::ClaimTypeMismatch(msg)) => assert!(msg.starts_with("$.gender: "), "{}", msg),
+            other => panic!("expected a type mismatch, got {:?}", other),
+        }
+    }
+}
@@ -5,12 +5,17 @@ use crate::utils::fixtures::{
     NESTED_ARRAY_JSONPATH, W3C_VC_CLAIMS, W3C_VC_JSONPATH,
 };
 use jsonwebtoken::jwk::Jwk;
-use jsonwebtoken::{DecodingKey, EncodingKey};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use proptest::prelude::*;
 use rstest::{fixture, rstest};
+use sd_jwt_rs::disclosure::{DisclosureFormat, JsonSpacing, UnicodeEncoding};
+use sd_jwt_rs::holder::{KeyBindingJwtExtras, Presentation};
 use sd_jwt_rs::issuer::ClaimsForSelectiveDisclosureStrategy;
+use sd_jwt_rs::signer::{HolderSigner, SoftwareSigner};
 use sd_jwt_rs::{SDJWTHolder, SDJWTIssuer, SDJWTJson, SDJWTVerifier, SDJWTSerializationFormat};
-use sd_jwt_rs::{COMBINED_SERIALIZATION_FORMAT_SEPARATOR, DEFAULT_SIGNING_ALG};
+use sd_jwt_rs::{convert_serialization, COMBINED_SERIALIZATION_FORMAT_SEPARATOR, DEFAULT_SIGNING_ALG};
 use serde_json::{json, Map, Value};
+use std::cell::Cell;
 use std::collections::HashSet;
 
 mod utils;
@@ -316,7 +321,7 @@ fn demo_positive_cases(
         .unwrap();
     let issued = sd_jwt.clone();
     // Holder creates presentation
-    let mut holder = SDJWTHolder::new(sd_jwt.clone(), format.clone()).unwrap();
+    let holder = SDJWTHolder::new(sd_jwt.clone(), format.clone()).unwrap();
     let presentation = holder
         .create_presentation(
             holder_disclosed_claims,
@@ -325,7 +330,8 @@ fn demo_positive_cases(
             holder_key,
             sign_algo,
         )
-        .unwrap();
+        .unwrap()
+        .to_string();
 
     if format == SDJWTSerializationFormat::Compact {
         let mut issued_parts: HashSet<&str> = issued
@@ -381,3 +387,244 @@ fn demo_positive_cases(
     )
         .unwrap();
 }
+
+/// Signs like a Secure Enclave would: the key never leaves the signer, only signatures do.
+struct ExternalSigner {
+    signer: SoftwareSigner,
+    signatures: Cell<usize>,
+}
+
+impl HolderSigner for ExternalSigner {
+    fn algorithm(&self) -> Algorithm {
+        self.signer.algorithm()
+    }
+
+    fn sign(&self, signing_input: &[u8]) -> sd_jwt_rs::error::Result<Vec<u8>> {
+        self.signatures.set(self.signatures.get() + 1);
+        self.signer.sign(signing_input)
+    }
+}
+
+#[rstest]
+fn demo_external_holder_signer(
+    issuer_key: EncodingKey,
+    #[values(SDJWTSerializationFormat::Compact, SDJWTSerializationFormat::JSON)] format: SDJWTSerializationFormat,
+) {
+    let user_claims = _address_claims();
+    let sd_jwt = SDJWTIssuer::new(issuer_key, None).issue_sd_jwt(
+        user_claims.clone(),
+        ClaimsForSelectiveDisclosureStrategy::TopLevel,
+        holder_jwk(),
+        false,
+        format.clone(),
+    )
+        .unwrap();
+    let signer = ExternalSigner {
+        signer: SoftwareSigner::new(holder_key().unwrap(), None).unwrap(),
+        signatures: Cell::new(0),
+    };
+
+    let holder = SDJWTHolder::new(sd_jwt, format.clone()).unwrap();
+    let presentation = holder
+        .create_presentation_with_signer(
+            user_claims.as_object().unwrap().clone(),
+            "1234567890",
+            "https://verifier.example.org",
+            &signer,
+            KeyBindingJwtExtras::default(),
+        )
+        .unwrap();
+    assert_eq!(signer.signatures.get(), 1);
+
+    let verified = SDJWTVerifier::new(
+        presentation.into(),
+        Box::new(|_, _| DecodingKey::from_ec_pem(ISSUER_PUBLIC_KEY.as_bytes()).unwrap()),
+        Some("https://verifier.example.org".to_string()),
+        Some("1234567890".to_string()),
+        format,
+    )
+        .unwrap();
+    assert_eq!(verified.verified_claims["address"], user_claims["address"]);
+}
+
+#[rstest]
+fn demo_many_presentations_from_one_holder(
+    issuer_key: EncodingKey,
+    #[values(SDJWTSerializationFormat::Compact, SDJWTSerializationFormat::JSON)] format: SDJWTSerializationFormat,
+) {
+    let user_claims = _address_claims();
+    let sd_jwt = SDJWTIssuer::new(issuer_key, None).issue_sd_jwt(
+        user_claims.clone(),
+        ClaimsForSelectiveDisclosureStrategy::TopLevel,
+        holder_jwk(),
+        false,
+        format.clone(),
+    )
+        .unwrap();
+    let holder = SDJWTHolder::new(sd_jwt, format.clone()).unwrap();
+
+    let presentations: Vec<(String, Presentation)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let holder = &holder;
+                scope.spawn(move || {
+                    let nonce = format!("nonce-{}", i);
+                    let claim_path = if i % 2 == 0 { "$.sub" } else { "$.address" };
+                    let presentation = holder
+                        .create_presentation_from_paths(
+                            &[claim_path],
+                            Some(nonce.clone()),
+                            Some("https://verifier.example.org".to_string()),
+                            holder_key(),
+                            None,
+                        )
+                        .unwrap();
+                    (nonce, presentation)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    for (i, (nonce, presentation)) in presentations.into_iter().enumerate() {
+        assert_eq!(presentation.disclosures().len(), 1);
+        assert!(presentation.key_binding_jwt().is_some());
+        let verified = SDJWTVerifier::new(
+            presentation.into(),
+            Box::new(|_, _| DecodingKey::from_ec_pem(ISSUER_PUBLIC_KEY.as_bytes()).unwrap()),
+            Some("https://verifier.example.org".to_string()),
+            Some(nonce),
+            format.clone(),
+        )
+            .unwrap();
+        let (disclosed, withheld) = if i % 2 == 0 { ("sub", "address") } else { ("address", "sub") };
+        assert_eq!(verified.verified_claims[disclosed], user_claims[disclosed]);
+        assert!(verified.verified_claims.get(withheld).is_none());
+    }
+}
+
+#[rstest]
+fn demo_detect_and_convert_serialization(
+    issuer_key: EncodingKey,
+    #[values(SDJWTSerializationFormat::Compact, SDJWTSerializationFormat::JSON)] format: SDJWTSerializationFormat,
+) {
+    let user_claims = _address_claims();
+    let sd_jwt = SDJWTIssuer::new(issuer_key, None).issue_sd_jwt(
+        user_claims.clone(),
+        ClaimsForSelectiveDisclosureStrategy::TopLevel,
+        holder_jwk(),
+        false,
+        format.clone(),
+    )
+        .unwrap();
+    let other_format = match format {
+        SDJWTSerializationFormat::Compact => SDJWTSerializationFormat::JSON,
+        SDJWTSerializationFormat::JSON => SDJWTSerializationFormat::Compact,
+    };
+
+    let converted = convert_serialization(&sd_jwt, other_format.clone()).unwrap();
+    assert_eq!(SDJWTSerializationFormat::detect(&converted).unwrap(), other_format);
+    assert_eq!(convert_serialization(&converted, format.clone()).unwrap(), sd_jwt);
+    assert_eq!(SDJWTVerifier::detect_signing_algorithm(&converted).unwrap(), DEFAULT_SIGNING_ALG);
+    assert!(SDJWTHolder::new(converted.clone(), format.clone()).is_err());
+
+    let holder = SDJWTHolder::new_with_detected_format(converted).unwrap();
+    let presentation = holder
+        .create_presentation(
+            user_claims.as_object().unwrap().clone(),
+            Some("1234567890".to_string()),
+            Some("https://verifier.example.org".to_string()),
+            holder_key(),
+            None,
+        )
+        .unwrap();
+
+    let presentation = convert_serialization(presentation.as_str(), format).unwrap();
+    let verified = SDJWTVerifier::new_with_detected_format(
+        presentation,
+        Box::new(|_, _| DecodingKey::from_ec_pem(ISSUER_PUBLIC_KEY.as_bytes()).unwrap()),
+        Some("https://verifier.example.org".to_string()),
+        Some("1234567890".to_string()),
+    )
+        .unwrap();
+    assert_eq!(verified.verified_claims["address"], user_claims["address"]);
+}
+
+/// Claim names with a meaning for the issuer or the verifier, which arbitrary claims must not use.
+const EXCLUDED_TEST_CLAIM_NAMES: [&str; 10] = ["_sd", "...", "_sd_alg", "cnf", "iss", "iat", "exp", "nbf", "aud", "status"];
+
+fn unicode_claims() -> impl Strategy<Value = Map<String, Value>> {
+    let name = any::<String>().prop_filter("excluded claim name", |name| !EXCLUDED_TEST_CLAIM_NAMES.contains(&name.as_str()));
+    let leaf = prop_oneof![
+        any::<String>().prop_map(Value::from),
+        "[,:\\[\\]{}\" aé😀]{0,12}".prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        any::<bool>().prop_map(Value::from),
+        Just(Value::Null),
+    ];
+    let object_name = name.clone();
+    let value = leaf.prop_recursive(3, 16, 3, move |value| {
+        prop_oneof![
+            prop::collection::vec(value.clone(), 1..3).prop_map(Value::from),
+            prop::collection::btree_map(object_name.clone(), value, 1..3)
+                .prop_map(|object| Value::Object(object.into_iter().collect())),
+        ]
+    });
+    prop::collection::btree_map(name, value, 1..4).prop_map(|claims| {
+        let mut claims: Map<String, Value> = claims.into_iter().collect();
+        claims.insert("iss".to_string(), json!("https://example.com/issuer"));
+        claims.insert("exp".to_string(), json!(1883000000));
+        claims
+    })
+}
+
+/// The claims to disclose for a holder to disclose every claim, every value is selected with `true`.
+fn disclose_all(claims: &Value) -> Value {
+    match claims {
+        Value::Array(elements) => elements.iter().map(disclose_all).collect(),
+        Value::Object(claims) => claims.iter().map(|(name, value)| (name.clone(), disclose_all(value))).collect(),
+        _ => Value::Bool(true),
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn demo_unicode_claims_round_trip(
+        user_claims in unicode_claims(),
+        utf8 in any::<bool>(),
+        compact in any::<bool>(),
+        json_format in any::<bool>(),
+    ) {
+        let format = if json_format { SDJWTSerializationFormat::JSON } else { SDJWTSerializationFormat::Compact };
+        let unicode = if utf8 { UnicodeEncoding::Utf8 } else { UnicodeEncoding::Escaped };
+        let spacing = if compact { JsonSpacing::Compact } else { JsonSpacing::Python };
+        let mut issuer = SDJWTIssuer::new(issuer_key(), None);
+        issuer.set_disclosure_format(DisclosureFormat { spacing, unicode });
+        let sd_jwt = issuer
+            .issue_sd_jwt(
+                Value::Object(user_claims.clone()),
+                ClaimsForSelectiveDisclosureStrategy::AllLevels,
+                None,
+                false,
+                format.clone(),
+            )
+            .unwrap();
+
+        let claims_to_disclose = disclose_all(&Value::Object(user_claims.clone()));
+        let presentation = SDJWTHolder::new(sd_jwt, format.clone())
+            .unwrap()
+            .create_presentation(claims_to_disclose.as_object().unwrap().clone(), None, None, None, None)
+            .unwrap();
+        let verified = SDJWTVerifier::new(
+            presentation.into(),
+            Box::new(|_, _| DecodingKey::from_ec_pem(ISSUER_PUBLIC_KEY.as_bytes()).unwrap()),
+            None,
+            None,
+            format,
+        )
+            .unwrap();
+        prop_assert_eq!(verified.verified_claims, Value::Object(user_claims));
+    }
+}
@@ -470,7 +470,7 @@ impl SDJWTIssuerWrapper {
 
 /// Wrapper for [SDJWTHolder]
 pub struct SDJWTHolderWrapper {
-    pub wrapped: Arc<Mutex<SDJWTHolder>>,
+    pub wrapped: Arc<SDJWTHolder>,
 }
 
 impl SDJWTHolderWrapper {
@@ -482,7 +482,7 @@ impl SDJWTHolderWrapper {
         let wrapped = SDJWTHolder::new(sd_jwt_with_disclosures, serialization_format)
             .map_err(|err| SDJWTError::Unspecified { message: err.to_string()})?;
         Ok(Self {
-            wrapped: Arc::new(Mutex::new(wrapped))
+            wrapped: Arc::new(wrapped)
         })
     }
 
@@ -521,16 +521,44 @@ impl SDJWTHolderWrapper {
             map_value.insert(key.clone(), value_to_insert);
         }
 
-        let mut locked = self.wrapped.lock().unwrap();
         return if let Some(val) = holder_key {
-            locked.create_presentation(map_value, nonce, aud, Option::from(val.core.clone()), sign_alg)
+            self.wrapped.create_presentation(map_value, nonce, aud, Option::from(val.core.clone()), sign_alg)
+                .map(String::from)
                 .map_err(|err| err.into())
         } else {
-            locked.create_presentation(map_value, nonce, aud, None, sign_alg)
+            self.wrapped.create_presentation(map_value, nonce, aud, None, sign_alg)
+                .map(String::from)
                 .map_err(|err| err.into())
         };
     }
 
+    /// Create a presentation revealing the claims identified by JSONPaths, see [SDJWTHolder::create_presentation_from_paths].
+    ///
+    /// # Arguments
+    /// * `claim_paths` - JSONPaths of the claims to disclose, e.g. `$.address.country`, or `$.address..*` to disclose a claim with all its nested claims
+    /// * `nonce` - Nonce to be used in the key-binding JWT
+    /// * `aud` - Audience to be used in the key-binding JWT
+    /// * `holder_key` - Key to sign the key-binding JWT
+    /// * `sign_alg` - Signing algorithm to be used in the key-binding JWT
+    ///
+    /// # Returns
+    /// * `String` - Presentation in the format specified by `serialization_format` in the constructor. It can be either compact or json.
+    pub fn create_presentation_from_paths(
+        &self,
+        claim_paths: Vec<String>,
+        nonce: Option<String>,
+        aud: Option<String>,
+        holder_key: Option<Arc<EncodingKeyValue>>,
+        sign_alg: Option<String>,
+    ) -> Result<String, SDJWTError> {
+        let claim_paths: Vec<&str> = claim_paths.iter().map(String::as_str).collect();
+        let holder_key = holder_key.map(|val| val.core.clone());
+
+        self.wrapped.create_presentation_from_paths(&claim_paths, nonce, aud, holder_key, sign_alg)
+            .map(String::from)
+            .map_err(|err| err.into())
+    }
+
     /// Converts a `Map<String, Value>` into a `HashMap<String, String>`.
     ///
     /// This function iterates over the elements of the provided map. For each key-value pair: